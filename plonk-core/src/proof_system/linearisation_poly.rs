@@ -0,0 +1,97 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) ZK-Garage. All rights reserved.
+//! Evaluations of proof polynomials at the round-4 challenge point `z`
+//! (and, where a widget needs it, `zω`), grouped by the widget that opens
+//! them. Every widget's `compute_linearisation_commitment` reads its slice
+//! of this struct out of the proof being verified.
+
+use ark_ff::PrimeField;
+use ark_serialize::*;
+
+/// Openings of the four wire polynomials.
+#[derive(CanonicalDeserialize, CanonicalSerialize, derivative::Derivative)]
+#[derivative(Clone, Debug, Default, Eq, PartialEq)]
+pub struct WireEvaluations<F>
+where
+    F: PrimeField,
+{
+    /// Evaluation of `a(X)` at `z`.
+    pub a_eval: F,
+    /// Evaluation of `b(X)` at `z`.
+    pub b_eval: F,
+    /// Evaluation of `c(X)` at `z`.
+    pub c_eval: F,
+    /// Evaluation of `d(X)` at `z`.
+    pub d_eval: F,
+    /// Evaluation of `a(X)` at `zω`.
+    pub a_next_eval: F,
+    /// Evaluation of `b(X)` at `zω`.
+    pub b_next_eval: F,
+    /// Evaluation of `d(X)` at `zω`.
+    pub d_next_eval: F,
+}
+
+/// Openings needed by the [`lookup`](crate::proof_system::widget::lookup)
+/// widget.
+#[derive(CanonicalDeserialize, CanonicalSerialize, derivative::Derivative)]
+#[derivative(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LookupEvaluations<F>
+where
+    F: PrimeField,
+{
+    /// Evaluation of the lookup selector at `z`.
+    pub q_lookup_eval: F,
+    /// Evaluation of the compressed-query polynomial at `z`.
+    pub f_eval: F,
+    /// Evaluation of the table polynomial at `z`.
+    pub table_eval: F,
+    /// Evaluation of the table polynomial at `zω`.
+    pub table_next_eval: F,
+    /// Evaluation of `h1(X)` at `z`.
+    pub h1_eval: F,
+    /// Evaluation of `h2(X)` at `z`.
+    pub h2_eval: F,
+    /// Evaluation of the lookup accumulator `z2(X)` at `zω`.
+    pub z2_next_eval: F,
+}
+
+/// Openings needed by the [`shuffle`](crate::proof_system::widget::shuffle)
+/// widget.
+#[derive(CanonicalDeserialize, CanonicalSerialize, derivative::Derivative)]
+#[derivative(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ShuffleEvaluations<F>
+where
+    F: PrimeField,
+{
+    /// Evaluation of shuffle column 1 at `z`.
+    pub shuffle_1_eval: F,
+    /// Evaluation of shuffle column 2 at `z`.
+    pub shuffle_2_eval: F,
+    /// Evaluation of shuffle column 3 at `z`.
+    pub shuffle_3_eval: F,
+    /// Evaluation of shuffle column 4 at `z`.
+    pub shuffle_4_eval: F,
+    /// Evaluation of the shuffle accumulator `z(X)` at `z`.
+    pub z_shuffle_eval: F,
+    /// Evaluation of the shuffle accumulator `z(X)` at `zω`.
+    pub z_shuffle_next_eval: F,
+}
+
+/// All evaluations a verifier needs to reconstruct every widget's
+/// linearisation commitment.
+#[derive(CanonicalDeserialize, CanonicalSerialize, derivative::Derivative)]
+#[derivative(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ProofEvaluations<F>
+where
+    F: PrimeField,
+{
+    /// Wire polynomial openings.
+    pub wire_evals: WireEvaluations<F>,
+    /// Lookup widget openings.
+    pub lookup_evals: LookupEvaluations<F>,
+    /// Shuffle widget openings.
+    pub shuffle_evals: ShuffleEvaluations<F>,
+}
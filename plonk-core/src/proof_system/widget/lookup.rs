@@ -8,12 +8,67 @@
 use crate::error::Error;
 use crate::lookup::multiset::MultiSet;
 use crate::proof_system::linearisation_poly::ProofEvaluations;
+use crate::transcript::Transcript;
 use crate::util::lc;
-use ark_ff::{FftField, PrimeField};
+use ark_ff::{FftField, PrimeField, UniformRand};
 use ark_poly::polynomial::univariate::DensePolynomial;
-use ark_poly::{EvaluationDomain, Evaluations, GeneralEvaluationDomain};
+use ark_poly::{EvaluationDomain, Evaluations, GeneralEvaluationDomain, UVPolynomial};
 use ark_poly_commit::PolynomialCommitment;
 use ark_serialize::*;
+use ark_std::rand::RngCore;
+use rayon::prelude::*;
+
+/// Tunes how [`ProverKey::compute_lookup_quotient_term`] splits its
+/// `4n`-sized range of evaluations across worker threads.
+///
+/// There is deliberately no `Default` impl: a chunk size that isn't a
+/// function of the range being split either wastes parallelism (chunks
+/// much smaller than `range / num_threads`) or serialises everything
+/// (chunks much larger), so every `ParallelConfig` is built from the
+/// domain size it will actually split - [`ParallelConfig::new`] for an
+/// explicit thread count, or [`ParallelConfig::for_domain`] to size off
+/// the global rayon thread pool. Pass a `chunk_size` equal to the domain
+/// size to run single-threaded, e.g. on environments where spinning up a
+/// thread pool is undesirable.
+///
+/// `chunk_size` is private so every `ParallelConfig` goes through a
+/// constructor that clamps it to at least `1` - `par_chunks_mut` panics
+/// on a `0` chunk size, and every constructor here is a chokepoint that
+/// can enforce that instead of leaving it to callers.
+#[derive(Clone, Copy, Debug)]
+pub struct ParallelConfig {
+    chunk_size: usize,
+}
+
+impl ParallelConfig {
+    /// Size chunks so that each of `num_threads` workers gets roughly one
+    /// `domain_size / num_threads` chunk.
+    pub fn new(domain_size: usize, num_threads: usize) -> Self {
+        let num_threads = num_threads.max(1);
+        Self::with_chunk_size(
+            (domain_size + num_threads - 1) / num_threads,
+        )
+    }
+
+    /// Size chunks off the global rayon thread pool for a range of
+    /// `domain_size` evaluation points.
+    pub fn for_domain(domain_size: usize) -> Self {
+        Self::new(domain_size, rayon::current_num_threads())
+    }
+
+    /// Builds a config from an explicit chunk size, clamped to at least
+    /// `1`.
+    pub fn with_chunk_size(chunk_size: usize) -> Self {
+        Self {
+            chunk_size: chunk_size.max(1),
+        }
+    }
+
+    /// Number of evaluation points handed to each worker at a time.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+}
 
 /// Lookup Gates Prover Key
 #[derive(CanonicalDeserialize, CanonicalSerialize, derivative::Derivative)]
@@ -39,6 +94,7 @@ where
     F: PrimeField,
 {
     /// Compute lookup portion of quotient polynomial
+    #[allow(clippy::too_many_arguments)]
     pub fn compute_lookup_quotient_term(
         &self,
         domain: &GeneralEvaluationDomain<F>,
@@ -56,6 +112,7 @@ where
         epsilon: F,
         zeta: F,
         lookup_sep: F,
+        parallel_config: Option<ParallelConfig>,
     ) -> Result<Vec<F>, Error>
     where
         F: PrimeField,
@@ -67,30 +124,42 @@ where
             <<F as FftField>::FftParams as ark_ff::FftParameters>::TWO_ADICITY,
     })?;
 
-        Ok((0..domain_4n.size())
-            .map(|i| {
-                self.compute_quotient_i(
-                    i,
-                    wl_eval_4n[i],
-                    wr_eval_4n[i],
-                    wo_eval_4n[i],
-                    w4_eval_4n[i],
-                    f_eval_4n[i],
-                    table_eval_4n[i],
-                    table_eval_4n[i + 4],
-                    h1_eval_4n[i],
-                    h1_eval_4n[i + 4],
-                    h2_eval_4n[i],
-                    z2_eval_4n[i],
-                    z2_eval_4n[i + 4],
-                    l1_eval_4n[i],
-                    delta,
-                    epsilon,
-                    zeta,
-                    lookup_sep,
-                )
-            })
-            .collect())
+        let parallel_config = parallel_config
+            .unwrap_or_else(|| ParallelConfig::for_domain(domain_4n.size()));
+
+        let mut quotient = vec![F::zero(); domain_4n.size()];
+
+        quotient
+            .par_chunks_mut(parallel_config.chunk_size())
+            .enumerate()
+            .for_each(|(chunk_index, out)| {
+                let start = chunk_index * parallel_config.chunk_size();
+                for (offset, slot) in out.iter_mut().enumerate() {
+                    let i = start + offset;
+                    *slot = self.compute_quotient_i(
+                        i,
+                        wl_eval_4n[i],
+                        wr_eval_4n[i],
+                        wo_eval_4n[i],
+                        w4_eval_4n[i],
+                        f_eval_4n[i],
+                        table_eval_4n[i],
+                        table_eval_4n[i + 4],
+                        h1_eval_4n[i],
+                        h1_eval_4n[i + 4],
+                        h2_eval_4n[i],
+                        z2_eval_4n[i],
+                        z2_eval_4n[i + 4],
+                        l1_eval_4n[i],
+                        delta,
+                        epsilon,
+                        zeta,
+                        lookup_sep,
+                    );
+                }
+            });
+
+        Ok(quotient)
     }
 
     /// Compute evals of lookup portion of quotient polynomial
@@ -208,6 +277,64 @@ where
     fn compress(w_l: F, w_r: F, w_o: F, w_4: F, zeta: F) -> F {
         lc(vec![w_l, w_r, w_o, w_4], zeta)
     }
+
+    /// Blinds `poly` for zero-knowledge, ahead of committing to it.
+    ///
+    /// When the `hiding` flag on the prover is set, every polynomial that
+    /// gets opened (here: `z2`, `h1`, `h2`) should be blinded before it is
+    /// committed to, or its opened evaluations leak information about the
+    /// witness. This adds `b(X) * Z_H(X)` to `poly`, where `Z_H` is the
+    /// vanishing polynomial of `domain` and `b` has one random
+    /// coefficient per distinct point `poly` will be opened at
+    /// (`num_openings`: 2 for `z2`, since it is opened at both `z` and
+    /// `zω`). Because `Z_H` vanishes on `domain`, this changes none of
+    /// the `compute_quotient_i` evaluations above, nor the verifier: only
+    /// the prover key construction and commitment step need to call it.
+    pub fn blind_opened_polynomial<R>(
+        poly: &DensePolynomial<F>,
+        num_openings: usize,
+        domain: &GeneralEvaluationDomain<F>,
+        rng: &mut R,
+    ) -> DensePolynomial<F>
+    where
+        R: RngCore,
+    {
+        let blinding_poly = DensePolynomial::from_coefficients_vec(
+            (0..num_openings).map(|_| F::rand(rng)).collect(),
+        );
+        let vanishing_poly: DensePolynomial<F> =
+            domain.vanishing_polynomial().into();
+
+        poly + &(&blinding_poly * &vanishing_poly)
+    }
+
+    /// Applies [`ProverKey::blind_opened_polynomial`] to the lookup
+    /// accumulator and sorted halves ahead of committing to them, if and
+    /// only if `hiding` is set - mirroring the blinding flag other
+    /// commitment schemes expose (e.g. `PC::commit`'s `hiding_bound`).
+    /// `z2` is opened at both `z` and `zω`, so it needs two blinding
+    /// coefficients; `h1`/`h2` are each opened once.
+    pub fn hide_opened_polynomials_if_needed<R>(
+        hiding: bool,
+        domain: &GeneralEvaluationDomain<F>,
+        z2_poly: DensePolynomial<F>,
+        h1_poly: DensePolynomial<F>,
+        h2_poly: DensePolynomial<F>,
+        rng: &mut R,
+    ) -> (DensePolynomial<F>, DensePolynomial<F>, DensePolynomial<F>)
+    where
+        R: RngCore,
+    {
+        if !hiding {
+            return (z2_poly, h1_poly, h2_poly);
+        }
+
+        (
+            Self::blind_opened_polynomial(&z2_poly, 2, domain, rng),
+            Self::blind_opened_polynomial(&h1_poly, 1, domain, rng),
+            Self::blind_opened_polynomial(&h2_poly, 1, domain, rng),
+        )
+    }
 }
 
 /// LookUp Verifier Key
@@ -234,16 +361,30 @@ where
     PC: PolynomialCommitment<F, DensePolynomial<F>>,
 {
     /// Computes the linearisation commitments.
-    pub fn compute_linearisation_commitment(
+    ///
+    /// Absorbs `z2_comm` and `h1_comm` - the lookup accumulator and first
+    /// sorted-halves commitments, which the prover must have already sent
+    /// by this round - and squeezes `delta`, `epsilon` and `zeta` from
+    /// `transcript` rather than taking them as arguments, so the verifier
+    /// always derives the same challenges the prover used to build them.
+    pub fn compute_linearisation_commitment<T>(
         &self,
+        transcript: &mut T,
         scalars: &mut Vec<F>,
         points: &mut Vec<PC::Commitment>,
         evaluations: &ProofEvaluations<F>,
-        (delta, epsilon, zeta): (F, F, F),
         l1_eval: F,
         z2_comm: PC::Commitment,
         h1_comm: PC::Commitment,
-    ) {
+    ) where
+        T: Transcript<F>,
+    {
+        transcript.append_commitment(b"z2_comm", &z2_comm);
+        transcript.append_commitment(b"h1_comm", &h1_comm);
+        let delta = transcript.challenge_scalar(b"delta");
+        let epsilon = transcript.challenge_scalar(b"epsilon");
+        let zeta = transcript.challenge_scalar(b"zeta");
+
         // f =q_lookup * (lc([a_eval, b_eval, c_eval, d_eval] , zeta) - f_eval)
         // lookup_sep is the eval of q_lookup and should be in the proof
 
@@ -291,3 +432,193 @@ where
         points.push(h1_comm);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr;
+    use ark_poly::Polynomial;
+    use ark_std::test_rng;
+
+    fn random_prover_key(domain: &GeneralEvaluationDomain<Fr>) -> ProverKey<Fr> {
+        let rng = &mut test_rng();
+        // `compute_quotient_i` indexes `q_lookup.1` across the whole `4n`
+        // range, so the selector evaluations live on the `4n` domain.
+        let domain_4n =
+            GeneralEvaluationDomain::<Fr>::new(4 * domain.size()).unwrap();
+        let q_lookup_evals: Vec<Fr> =
+            (0..domain_4n.size()).map(|_| Fr::rand(rng)).collect();
+        let mut column = || {
+            let evals: Vec<Fr> =
+                (0..domain.size()).map(|_| Fr::rand(rng)).collect();
+            MultiSet(evals)
+        };
+        ProverKey {
+            q_lookup: (
+                DensePolynomial::from_coefficients_vec(q_lookup_evals.clone()),
+                Evaluations::from_vec_and_domain(q_lookup_evals, domain_4n),
+            ),
+            table_1: column(),
+            table_2: column(),
+            table_3: column(),
+            table_4: column(),
+        }
+    }
+
+    #[test]
+    fn chunked_quotient_term_matches_single_chunk() {
+        let domain = GeneralEvaluationDomain::<Fr>::new(8).unwrap();
+        let pk = random_prover_key(&domain);
+        let domain_4n_size = 4 * domain.size();
+        // `compute_lookup_quotient_term` reads `i + 4`, so every `*_eval_4n` slice
+        // needs `domain_4n_size + 4` entries for the wrap-around reads at
+        // the end of the range.
+        let padded_len = domain_4n_size + 4;
+
+        let rng = &mut test_rng();
+        let wl: Vec<Fr> = (0..padded_len).map(|_| Fr::rand(rng)).collect();
+        let wr: Vec<Fr> = (0..padded_len).map(|_| Fr::rand(rng)).collect();
+        let wo: Vec<Fr> = (0..padded_len).map(|_| Fr::rand(rng)).collect();
+        let w4: Vec<Fr> = (0..padded_len).map(|_| Fr::rand(rng)).collect();
+        let f: Vec<Fr> = (0..padded_len).map(|_| Fr::rand(rng)).collect();
+        let table: Vec<Fr> = (0..padded_len).map(|_| Fr::rand(rng)).collect();
+        let h1: Vec<Fr> = (0..padded_len).map(|_| Fr::rand(rng)).collect();
+        let h2: Vec<Fr> = (0..padded_len).map(|_| Fr::rand(rng)).collect();
+        let z2: Vec<Fr> = (0..padded_len).map(|_| Fr::rand(rng)).collect();
+        let l1: Vec<Fr> = (0..padded_len).map(|_| Fr::rand(rng)).collect();
+
+        let delta = Fr::rand(rng);
+        let epsilon = Fr::rand(rng);
+        let zeta = Fr::rand(rng);
+        let lookup_sep = Fr::rand(rng);
+
+        let single_chunk = pk
+            .compute_lookup_quotient_term(
+                &domain,
+                &wl,
+                &wr,
+                &wo,
+                &w4,
+                &f,
+                &table,
+                &h1,
+                &h2,
+                &z2,
+                &l1,
+                delta,
+                epsilon,
+                zeta,
+                lookup_sep,
+                Some(ParallelConfig::with_chunk_size(domain_4n_size)),
+            )
+            .unwrap();
+
+        let many_small_chunks = pk
+            .compute_lookup_quotient_term(
+                &domain,
+                &wl,
+                &wr,
+                &wo,
+                &w4,
+                &f,
+                &table,
+                &h1,
+                &h2,
+                &z2,
+                &l1,
+                delta,
+                epsilon,
+                zeta,
+                lookup_sep,
+                Some(ParallelConfig::with_chunk_size(3)),
+            )
+            .unwrap();
+
+        let default_chunking = pk
+            .compute_lookup_quotient_term(
+                &domain, &wl, &wr, &wo, &w4, &f, &table, &h1, &h2, &z2, &l1,
+                delta, epsilon, zeta, lookup_sep, None,
+            )
+            .unwrap();
+
+        assert_eq!(single_chunk, many_small_chunks);
+        assert_eq!(single_chunk, default_chunking);
+    }
+
+    #[test]
+    fn default_chunk_size_scales_with_the_domain() {
+        let small = ParallelConfig::for_domain(64);
+        let large = ParallelConfig::for_domain(1 << 20);
+
+        // A fixed, domain-independent chunk size would make these equal;
+        // the chunk size should instead stay roughly proportional to
+        // `domain_size / num_threads` in both cases.
+        assert!(large.chunk_size() >= small.chunk_size());
+        assert!(
+            large.chunk_size() * rayon::current_num_threads() >= (1 << 20) / 2
+        );
+    }
+
+    #[test]
+    fn with_chunk_size_clamps_zero_to_one() {
+        assert_eq!(ParallelConfig::with_chunk_size(0).chunk_size(), 1);
+    }
+
+    #[test]
+    fn blinding_agrees_with_the_original_polynomial_on_the_domain() {
+        let domain = GeneralEvaluationDomain::<Fr>::new(8).unwrap();
+        let rng = &mut test_rng();
+        let poly = DensePolynomial::from_coefficients_vec(
+            (0..5).map(|_| Fr::rand(rng)).collect(),
+        );
+
+        let blinded =
+            ProverKey::blind_opened_polynomial(&poly, 2, &domain, rng);
+
+        for point in domain.elements() {
+            assert_eq!(poly.evaluate(&point), blinded.evaluate(&point));
+        }
+        assert_ne!(poly, blinded);
+    }
+
+    #[test]
+    fn hiding_flag_toggles_whether_z2_h1_h2_get_blinded() {
+        let domain = GeneralEvaluationDomain::<Fr>::new(8).unwrap();
+        let rng = &mut test_rng();
+        let z2 = DensePolynomial::from_coefficients_vec(
+            (0..5).map(|_| Fr::rand(rng)).collect(),
+        );
+        let h1 = DensePolynomial::from_coefficients_vec(
+            (0..5).map(|_| Fr::rand(rng)).collect(),
+        );
+        let h2 = DensePolynomial::from_coefficients_vec(
+            (0..5).map(|_| Fr::rand(rng)).collect(),
+        );
+
+        let (z2_out, h1_out, h2_out) =
+            ProverKey::hide_opened_polynomials_if_needed(
+                false,
+                &domain,
+                z2.clone(),
+                h1.clone(),
+                h2.clone(),
+                rng,
+            );
+        assert_eq!(z2, z2_out);
+        assert_eq!(h1, h1_out);
+        assert_eq!(h2, h2_out);
+
+        let (z2_hidden, h1_hidden, h2_hidden) =
+            ProverKey::hide_opened_polynomials_if_needed(
+                true, &domain, z2.clone(), h1.clone(), h2.clone(), rng,
+            );
+        assert_ne!(z2, z2_hidden);
+        assert_ne!(h1, h1_hidden);
+        assert_ne!(h2, h2_hidden);
+        for point in domain.elements() {
+            assert_eq!(z2.evaluate(&point), z2_hidden.evaluate(&point));
+            assert_eq!(h1.evaluate(&point), h1_hidden.evaluate(&point));
+            assert_eq!(h2.evaluate(&point), h2_hidden.evaluate(&point));
+        }
+    }
+}
@@ -0,0 +1,14 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) ZK-Garage. All rights reserved.
+//! Gate widgets. Each widget owns a slice of the prover/verifier key and
+//! contributes its own term to the quotient and linearisation
+//! polynomials.
+
+/// Plookup: proves membership of the wire tuple in a fixed table.
+pub mod lookup;
+/// Shuffle: proves the wire tuple is a multiset-permutation of another
+/// tuple of advice columns.
+pub mod shuffle;
@@ -0,0 +1,628 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) ZK-Garage. All rights reserved.
+//! Shuffle gates
+//!
+//! Proves that the tuple `(w_l, w_r, w_o, w_4)` is a multiset-permutation
+//! of another tuple of advice columns, without requiring either side to be
+//! a fixed table. Where [`lookup`](crate::proof_system::widget::lookup)
+//! needs sorted halves `h1`/`h2` to prove membership in a static table,
+//! a shuffle only needs a single grand-product accumulator, since both
+//! sides are witnessed and can be compressed with the same random linear
+//! combination.
+
+use crate::error::Error;
+use crate::proof_system::linearisation_poly::ProofEvaluations;
+use crate::transcript::Transcript;
+use crate::util::lc;
+use ark_ff::{FftField, PrimeField};
+use ark_poly::polynomial::univariate::DensePolynomial;
+use ark_poly::{EvaluationDomain, Evaluations, GeneralEvaluationDomain};
+use ark_poly_commit::PolynomialCommitment;
+use ark_serialize::*;
+
+/// Shuffle Gates Prover Key
+#[derive(CanonicalDeserialize, CanonicalSerialize, derivative::Derivative)]
+#[derivative(Clone, Debug, Eq, PartialEq)]
+pub struct ProverKey<F>
+where
+    F: PrimeField,
+{
+    /// Shuffle selector
+    pub q_shuffle: (DensePolynomial<F>, Evaluations<F>),
+    /// Column 1 of the shuffled tuple
+    pub shuffle_1: (DensePolynomial<F>, Evaluations<F>),
+    /// Column 2 of the shuffled tuple
+    pub shuffle_2: (DensePolynomial<F>, Evaluations<F>),
+    /// Column 3 of the shuffled tuple
+    pub shuffle_3: (DensePolynomial<F>, Evaluations<F>),
+    /// Column 4 of the shuffled tuple
+    pub shuffle_4: (DensePolynomial<F>, Evaluations<F>),
+}
+
+impl<F> ProverKey<F>
+where
+    F: PrimeField,
+{
+    /// Compute shuffle portion of quotient polynomial
+    #[allow(clippy::too_many_arguments)]
+    pub fn compute_shuffle_quotient_term(
+        &self,
+        domain: &GeneralEvaluationDomain<F>,
+        wl_eval_4n: &[F],
+        wr_eval_4n: &[F],
+        wo_eval_4n: &[F],
+        w4_eval_4n: &[F],
+        z_shuffle_eval_4n: &[F],
+        l1_eval_4n: &[F],
+        ln_eval_4n: &[F],
+        gamma: F,
+        zeta: F,
+        shuffle_sep: F,
+    ) -> Result<Vec<F>, Error>
+    where
+        F: PrimeField,
+    {
+        let domain_4n = GeneralEvaluationDomain::<F>::new(4 * domain.size())
+            .ok_or(Error::InvalidEvalDomainSize {
+                log_size_of_group: (4 * domain.size()).trailing_zeros(),
+                adicity:
+                    <<F as FftField>::FftParams as ark_ff::FftParameters>::TWO_ADICITY,
+            })?;
+
+        let shuffle_1_eval_4n = &self.shuffle_1.1;
+        let shuffle_2_eval_4n = &self.shuffle_2.1;
+        let shuffle_3_eval_4n = &self.shuffle_3.1;
+        let shuffle_4_eval_4n = &self.shuffle_4.1;
+
+        Ok((0..domain_4n.size())
+            .map(|i| {
+                self.compute_quotient_i(
+                    i,
+                    wl_eval_4n[i],
+                    wr_eval_4n[i],
+                    wo_eval_4n[i],
+                    w4_eval_4n[i],
+                    shuffle_1_eval_4n[i],
+                    shuffle_2_eval_4n[i],
+                    shuffle_3_eval_4n[i],
+                    shuffle_4_eval_4n[i],
+                    z_shuffle_eval_4n[i],
+                    z_shuffle_eval_4n[i + 4],
+                    l1_eval_4n[i],
+                    ln_eval_4n[i],
+                    gamma,
+                    zeta,
+                    shuffle_sep,
+                )
+            })
+            .collect())
+    }
+
+    /// Compute evals of shuffle portion of quotient polynomial
+    #[allow(clippy::too_many_arguments)]
+    pub fn compute_quotient_i(
+        &self,
+        index: usize,
+        w_l_i: F,
+        w_r_i: F,
+        w_o_i: F,
+        w_4_i: F,
+        shuffle_1_i: F,
+        shuffle_2_i: F,
+        shuffle_3_i: F,
+        shuffle_4_i: F,
+        z_shuffle_i: F,
+        z_shuffle_i_next: F,
+        l1_i: F,
+        ln_i: F,
+        gamma: F,
+        zeta: F,
+        shuffle_sep: F,
+    ) -> F {
+        let q_shuffle_i = self.q_shuffle.1[index];
+        let shuffle_sep_sq = shuffle_sep.square();
+        let shuffle_sep_cu = shuffle_sep_sq * shuffle_sep;
+
+        let lhs = Self::compress(w_l_i, w_r_i, w_o_i, w_4_i, zeta);
+        let rhs = Self::compress(
+            shuffle_1_i,
+            shuffle_2_i,
+            shuffle_3_i,
+            shuffle_4_i,
+            zeta,
+        );
+
+        // q_shuffle(X) * (z(Xω) * (γ + rhs) − z(X) * (γ + lhs)) * shuffle_sep
+        let a = q_shuffle_i
+            * (z_shuffle_i_next * (gamma + rhs) - z_shuffle_i * (gamma + lhs))
+            * shuffle_sep;
+
+        // (z(X) − 1) * L_1(X) * shuffle_sep^2
+        let b = (z_shuffle_i - F::one()) * l1_i * shuffle_sep_sq;
+
+        // (z(X) − 1) * L_n(X) * shuffle_sep^3
+        let c = (z_shuffle_i - F::one()) * ln_i * shuffle_sep_cu;
+
+        a + b + c
+    }
+
+    /// Compute linearization for shuffle gates
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn compute_linearisation(
+        &self,
+        l1_eval: F,
+        ln_eval: F,
+        a_eval: F,
+        b_eval: F,
+        c_eval: F,
+        d_eval: F,
+        shuffle_1_eval: F,
+        shuffle_2_eval: F,
+        shuffle_3_eval: F,
+        shuffle_4_eval: F,
+        z_shuffle_eval: F,
+        z_shuffle_next_eval: F,
+        gamma: F,
+        zeta: F,
+        z_shuffle_poly: &DensePolynomial<F>,
+        shuffle_separation_challenge: F,
+    ) -> DensePolynomial<F> {
+        let shuffle_sep_sq = shuffle_separation_challenge.square();
+        let shuffle_sep_cu = shuffle_separation_challenge * shuffle_sep_sq;
+
+        let lhs = Self::compress(a_eval, b_eval, c_eval, d_eval, zeta);
+        let rhs = Self::compress(
+            shuffle_1_eval,
+            shuffle_2_eval,
+            shuffle_3_eval,
+            shuffle_4_eval,
+            zeta,
+        );
+
+        // q_shuffle(X) * (z(Xω)_bar * (γ + rhs) − z(X)_bar * (γ + lhs)) * shuffle_sep
+        let a = {
+            let a_0 = z_shuffle_next_eval * (gamma + rhs)
+                - z_shuffle_eval * (gamma + lhs);
+            &self.q_shuffle.0 * (a_0 * shuffle_separation_challenge)
+        };
+
+        // z(X) * (L_1_bar * shuffle_sep^2 + L_n_bar * shuffle_sep^3)
+        let b = z_shuffle_poly
+            * (l1_eval * shuffle_sep_sq + ln_eval * shuffle_sep_cu);
+
+        a + b
+    }
+
+    /// Compresses a row of values into a single field element by applying
+    /// a random linear combination, the same way
+    /// [`lookup`](crate::proof_system::widget::lookup) does.
+    fn compress(w_l: F, w_r: F, w_o: F, w_4: F, zeta: F) -> F {
+        lc(vec![w_l, w_r, w_o, w_4], zeta)
+    }
+}
+
+/// Shuffle Verifier Key
+#[derive(CanonicalDeserialize, CanonicalSerialize, derivative::Derivative)]
+#[derivative(
+    Clone,
+    Copy(bound = "PC::Commitment: Copy"),
+    Debug(bound = "PC::Commitment: std::fmt::Debug"),
+    Eq(bound = "PC::Commitment: Eq"),
+    PartialEq(bound = "PC::Commitment: PartialEq")
+)]
+pub struct VerifierKey<F, PC>
+where
+    F: PrimeField,
+    PC: PolynomialCommitment<F, DensePolynomial<F>>,
+{
+    /// Shuffle Selector Commitment
+    pub q_shuffle: PC::Commitment,
+    /// Column 1 Commitment
+    pub shuffle_1: PC::Commitment,
+    /// Column 2 Commitment
+    pub shuffle_2: PC::Commitment,
+    /// Column 3 Commitment
+    pub shuffle_3: PC::Commitment,
+    /// Column 4 Commitment
+    pub shuffle_4: PC::Commitment,
+}
+
+impl<F, PC> VerifierKey<F, PC>
+where
+    F: PrimeField,
+    PC: PolynomialCommitment<F, DensePolynomial<F>>,
+{
+    /// Computes the linearisation commitments.
+    ///
+    /// Absorbs `z_shuffle_comm` - the shuffle accumulator commitment - and
+    /// squeezes `gamma` and `zeta` from `transcript` rather than taking
+    /// them as arguments, matching
+    /// [`lookup::VerifierKey::compute_linearisation_commitment`](crate::proof_system::widget::lookup::VerifierKey::compute_linearisation_commitment).
+    #[allow(clippy::too_many_arguments)]
+    pub fn compute_linearisation_commitment<T>(
+        &self,
+        transcript: &mut T,
+        scalars: &mut Vec<F>,
+        points: &mut Vec<PC::Commitment>,
+        evaluations: &ProofEvaluations<F>,
+        l1_eval: F,
+        ln_eval: F,
+        shuffle_separation_challenge: F,
+        z_shuffle_comm: PC::Commitment,
+    ) where
+        T: Transcript<F>,
+    {
+        transcript.append_commitment(b"z_shuffle_comm", &z_shuffle_comm);
+        let gamma = transcript.challenge_scalar(b"gamma");
+        let zeta = transcript.challenge_scalar(b"zeta");
+
+        let shuffle_sep_sq = shuffle_separation_challenge.square();
+        let shuffle_sep_cu = shuffle_separation_challenge * shuffle_sep_sq;
+
+        let lhs = lc(
+            vec![
+                evaluations.wire_evals.a_eval,
+                evaluations.wire_evals.b_eval,
+                evaluations.wire_evals.c_eval,
+                evaluations.wire_evals.d_eval,
+            ],
+            zeta,
+        );
+        let rhs = lc(
+            vec![
+                evaluations.shuffle_evals.shuffle_1_eval,
+                evaluations.shuffle_evals.shuffle_2_eval,
+                evaluations.shuffle_evals.shuffle_3_eval,
+                evaluations.shuffle_evals.shuffle_4_eval,
+            ],
+            zeta,
+        );
+
+        let f = (evaluations.shuffle_evals.z_shuffle_next_eval * (gamma + rhs)
+            - evaluations.shuffle_evals.z_shuffle_eval * (gamma + lhs))
+            * shuffle_separation_challenge;
+
+        scalars.push(f);
+        points.push(self.q_shuffle.clone());
+
+        scalars.push(l1_eval * shuffle_sep_sq + ln_eval * shuffle_sep_cu);
+        points.push(z_shuffle_comm);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr;
+    use ark_ff::{One, Zero};
+    use ark_poly::UVPolynomial;
+
+    fn column(domain: &GeneralEvaluationDomain<Fr>, value: Fr) -> (DensePolynomial<Fr>, Evaluations<Fr>) {
+        let evals = Evaluations::from_vec_and_domain(
+            vec![value; domain.size()],
+            *domain,
+        );
+        (DensePolynomial::from_coefficients_vec(vec![value]), evals)
+    }
+
+    fn key(domain: &GeneralEvaluationDomain<Fr>, q_shuffle: Fr, shuffle: [Fr; 4]) -> ProverKey<Fr> {
+        ProverKey {
+            q_shuffle: column(domain, q_shuffle),
+            shuffle_1: column(domain, shuffle[0]),
+            shuffle_2: column(domain, shuffle[1]),
+            shuffle_3: column(domain, shuffle[2]),
+            shuffle_4: column(domain, shuffle[3]),
+        }
+    }
+
+    #[test]
+    fn quotient_vanishes_when_the_shuffle_and_boundary_conditions_hold() {
+        let domain = GeneralEvaluationDomain::<Fr>::new(4).unwrap();
+        let zeta = Fr::from(5u64);
+        let gamma = Fr::from(7u64);
+        let shuffle_sep = Fr::from(11u64);
+
+        let wires = [Fr::from(1u64), Fr::from(2u64), Fr::from(3u64), Fr::from(4u64)];
+        let shuffle_cols = wires; // same multiset, in the same order
+
+        let lhs = ProverKey::compress(wires[0], wires[1], wires[2], wires[3], zeta);
+        let rhs = ProverKey::compress(
+            shuffle_cols[0],
+            shuffle_cols[1],
+            shuffle_cols[2],
+            shuffle_cols[3],
+            zeta,
+        );
+        assert_eq!(lhs, rhs);
+
+        let z_i = Fr::from(1u64);
+        // z(Xω) * (γ + rhs) = z(X) * (γ + lhs) since lhs == rhs
+        let z_i_next = z_i;
+
+        let pk = key(&domain, Fr::one(), shuffle_cols);
+
+        let quotient_i = pk.compute_quotient_i(
+            0,
+            wires[0],
+            wires[1],
+            wires[2],
+            wires[3],
+            shuffle_cols[0],
+            shuffle_cols[1],
+            shuffle_cols[2],
+            shuffle_cols[3],
+            z_i,
+            z_i_next,
+            Fr::zero(), // not row 1
+            Fr::zero(), // not row n
+            gamma,
+            zeta,
+            shuffle_sep,
+        );
+
+        assert_eq!(quotient_i, Fr::zero());
+    }
+
+    #[test]
+    fn quotient_is_nonzero_when_the_shuffle_is_broken() {
+        let domain = GeneralEvaluationDomain::<Fr>::new(4).unwrap();
+        let zeta = Fr::from(5u64);
+        let gamma = Fr::from(7u64);
+        let shuffle_sep = Fr::from(11u64);
+
+        let wires = [Fr::from(1u64), Fr::from(2u64), Fr::from(3u64), Fr::from(4u64)];
+        // Not a permutation of `wires`.
+        let shuffle_cols = [Fr::from(9u64), Fr::from(9u64), Fr::from(9u64), Fr::from(9u64)];
+
+        let pk = key(&domain, Fr::one(), shuffle_cols);
+
+        let quotient_i = pk.compute_quotient_i(
+            0,
+            wires[0],
+            wires[1],
+            wires[2],
+            wires[3],
+            shuffle_cols[0],
+            shuffle_cols[1],
+            shuffle_cols[2],
+            shuffle_cols[3],
+            Fr::one(),
+            Fr::one(),
+            Fr::zero(),
+            Fr::zero(),
+            gamma,
+            zeta,
+            shuffle_sep,
+        );
+
+        assert_ne!(quotient_i, Fr::zero());
+    }
+
+    #[test]
+    fn boundary_terms_enforce_z_equals_one_at_the_first_and_last_row() {
+        let domain = GeneralEvaluationDomain::<Fr>::new(4).unwrap();
+        let pk = key(&domain, Fr::zero(), [Fr::zero(); 4]);
+
+        // q_shuffle = 0 isolates the L_1/L_n boundary terms from the
+        // per-row ratio term.
+        let quotient_at_l1 = pk.compute_quotient_i(
+            0,
+            Fr::zero(),
+            Fr::zero(),
+            Fr::zero(),
+            Fr::zero(),
+            Fr::zero(),
+            Fr::zero(),
+            Fr::zero(),
+            Fr::zero(),
+            Fr::from(2u64), // z != 1
+            Fr::from(2u64),
+            Fr::one(), // L_1(X) = 1
+            Fr::zero(),
+            Fr::from(7u64),
+            Fr::from(5u64),
+            Fr::from(11u64),
+        );
+        assert_ne!(quotient_at_l1, Fr::zero());
+
+        let quotient_at_ln = pk.compute_quotient_i(
+            0,
+            Fr::zero(),
+            Fr::zero(),
+            Fr::zero(),
+            Fr::zero(),
+            Fr::zero(),
+            Fr::zero(),
+            Fr::zero(),
+            Fr::zero(),
+            Fr::from(2u64), // z != 1
+            Fr::from(2u64),
+            Fr::zero(),
+            Fr::one(), // L_n(X) = 1
+            Fr::from(7u64),
+            Fr::from(5u64),
+            Fr::from(11u64),
+        );
+        assert_ne!(quotient_at_ln, Fr::zero());
+
+        let quotient_at_z_one = pk.compute_quotient_i(
+            0,
+            Fr::zero(),
+            Fr::zero(),
+            Fr::zero(),
+            Fr::zero(),
+            Fr::zero(),
+            Fr::zero(),
+            Fr::zero(),
+            Fr::zero(),
+            Fr::one(), // z == 1
+            Fr::one(),
+            Fr::one(),
+            Fr::zero(),
+            Fr::from(7u64),
+            Fr::from(5u64),
+            Fr::from(11u64),
+        );
+        assert_eq!(quotient_at_z_one, Fr::zero());
+    }
+
+    fn evaluations_for(
+        wires: [Fr; 4],
+        shuffle_cols: [Fr; 4],
+        z_shuffle_eval: Fr,
+        z_shuffle_next_eval: Fr,
+    ) -> ProofEvaluations<Fr> {
+        ProofEvaluations {
+            wire_evals: crate::proof_system::linearisation_poly::WireEvaluations {
+                a_eval: wires[0],
+                b_eval: wires[1],
+                c_eval: wires[2],
+                d_eval: wires[3],
+                ..Default::default()
+            },
+            shuffle_evals: crate::proof_system::linearisation_poly::ShuffleEvaluations {
+                shuffle_1_eval: shuffle_cols[0],
+                shuffle_2_eval: shuffle_cols[1],
+                shuffle_3_eval: shuffle_cols[2],
+                shuffle_4_eval: shuffle_cols[3],
+                z_shuffle_eval,
+                z_shuffle_next_eval,
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn linearisation_matches_quotient_term_coefficient_at_z_shuffle_ne_one() {
+        let domain = GeneralEvaluationDomain::<Fr>::new(4).unwrap();
+        let zeta = Fr::from(5u64);
+        let gamma = Fr::from(7u64);
+        let shuffle_sep = Fr::from(11u64);
+
+        let wires = [Fr::from(1u64), Fr::from(2u64), Fr::from(3u64), Fr::from(4u64)];
+        // Not a permutation of `wires`, so lhs != rhs and the missing
+        // `z_shuffle_eval` factor is distinguishable.
+        let shuffle_cols = [Fr::from(9u64), Fr::from(9u64), Fr::from(9u64), Fr::from(9u64)];
+        let z_shuffle_eval = Fr::from(3u64);
+        let z_shuffle_next_eval = Fr::from(13u64);
+
+        let pk = key(&domain, Fr::one(), shuffle_cols);
+        let z_shuffle_poly =
+            DensePolynomial::from_coefficients_vec(vec![Fr::zero()]);
+
+        let lin = pk.compute_linearisation(
+            Fr::zero(),
+            Fr::zero(),
+            wires[0],
+            wires[1],
+            wires[2],
+            wires[3],
+            shuffle_cols[0],
+            shuffle_cols[1],
+            shuffle_cols[2],
+            shuffle_cols[3],
+            z_shuffle_eval,
+            z_shuffle_next_eval,
+            gamma,
+            zeta,
+            &z_shuffle_poly,
+            shuffle_sep,
+        );
+
+        let lhs = ProverKey::compress(wires[0], wires[1], wires[2], wires[3], zeta);
+        let rhs = ProverKey::compress(
+            shuffle_cols[0],
+            shuffle_cols[1],
+            shuffle_cols[2],
+            shuffle_cols[3],
+            zeta,
+        );
+        let expected_a0 = z_shuffle_next_eval * (gamma + rhs)
+            - z_shuffle_eval * (gamma + lhs);
+        let expected = &pk.q_shuffle.0 * (expected_a0 * shuffle_sep);
+
+        assert_eq!(lin, expected);
+    }
+
+    #[test]
+    fn linearisation_commitment_absorbs_z_shuffle_comm_and_matches_quotient_term(
+    ) {
+        use crate::transcript::blake2b::Blake2bTranscript;
+        use ark_bls12_381::G1Affine;
+        use ark_poly_commit::ipa_pc::InnerProductArgPC;
+        use ark_poly_commit::{LabeledPolynomial, PolynomialCommitment};
+
+        type PC = InnerProductArgPC<G1Affine, blake2::Blake2s, DensePolynomial<Fr>>;
+
+        let rng = &mut ark_std::test_rng();
+        let pp = PC::setup(4, None, rng).unwrap();
+        let (ck, _vk) = PC::trim(&pp, 4, 0, None).unwrap();
+        let poly = LabeledPolynomial::new(
+            "z_shuffle".to_string(),
+            DensePolynomial::from_coefficients_vec(vec![Fr::one()]),
+            None,
+            None,
+        );
+        let (comms, _rands) = PC::commit(&ck, [&poly], Some(rng)).unwrap();
+        let commitment = comms[0].commitment().clone();
+
+        let vk = VerifierKey::<Fr, PC> {
+            q_shuffle: commitment.clone(),
+            shuffle_1: commitment.clone(),
+            shuffle_2: commitment.clone(),
+            shuffle_3: commitment.clone(),
+            shuffle_4: commitment.clone(),
+        };
+
+        let wires = [Fr::from(1u64), Fr::from(2u64), Fr::from(3u64), Fr::from(4u64)];
+        let shuffle_cols = [Fr::from(9u64), Fr::from(9u64), Fr::from(9u64), Fr::from(9u64)];
+        let z_shuffle_eval = Fr::from(3u64);
+        let z_shuffle_next_eval = Fr::from(13u64);
+        let evaluations = evaluations_for(
+            wires,
+            shuffle_cols,
+            z_shuffle_eval,
+            z_shuffle_next_eval,
+        );
+        let l1_eval = Fr::from(2u64);
+        let ln_eval = Fr::from(4u64);
+        let shuffle_sep = Fr::from(11u64);
+
+        let mut scalars = Vec::new();
+        let mut points = Vec::new();
+        let mut transcript = Blake2bTranscript::new(b"shuffle-test");
+        vk.compute_linearisation_commitment(
+            &mut transcript,
+            &mut scalars,
+            &mut points,
+            &evaluations,
+            l1_eval,
+            ln_eval,
+            shuffle_sep,
+            commitment.clone(),
+        );
+
+        let mut expected_transcript = Blake2bTranscript::new(b"shuffle-test");
+        expected_transcript.append_commitment(b"z_shuffle_comm", &commitment);
+        let gamma: Fr = expected_transcript.challenge_scalar(b"gamma");
+        let zeta: Fr = expected_transcript.challenge_scalar(b"zeta");
+
+        let lhs = ProverKey::compress(wires[0], wires[1], wires[2], wires[3], zeta);
+        let rhs = ProverKey::compress(
+            shuffle_cols[0],
+            shuffle_cols[1],
+            shuffle_cols[2],
+            shuffle_cols[3],
+            zeta,
+        );
+        let expected_f = (z_shuffle_next_eval * (gamma + rhs)
+            - z_shuffle_eval * (gamma + lhs))
+            * shuffle_sep;
+
+        assert_eq!(scalars[0], expected_f);
+        assert_eq!(points[0], commitment);
+    }
+}
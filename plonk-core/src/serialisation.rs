@@ -0,0 +1,133 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) ZK-Garage. All rights reserved.
+//! Versioned on-disk format for proving/verifying keys and proofs.
+//!
+//! Individual widgets derive [`CanonicalSerialize`]/[`CanonicalDeserialize`]
+//! on their own keys (or, for some legacy widgets, don't), which is enough
+//! to round-trip a single struct but gives callers no way to tell a
+//! verifying key apart from a proof on disk, or to notice that a key was
+//! produced for a different curve or an older format before
+//! deserialisation decodes it into the wrong thing. [`Serializable`] adds
+//! a small fixed header - magic bytes, a format version, and a
+//! curve/commitment-scheme identifier - ahead of the compressed
+//! `CanonicalSerialize` body, so a reader can reject a mismatched or
+//! stale artifact outright instead of failing deep inside deserialisation.
+
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write,
+};
+
+/// Bytes written at the start of every artifact produced by
+/// [`Serializable::write_versioned`], so a reader can reject a file that
+/// is not one of ours before attempting to deserialise it.
+const MAGIC: [u8; 4] = *b"PLNK";
+
+/// Current on-disk format version. Bump this whenever the encoding of
+/// [`Header`] or the body written after it changes incompatibly.
+const FORMAT_VERSION: u16 = 1;
+
+/// Identifies the curve and commitment scheme an artifact was produced
+/// under, e.g. `"bls12-381/kzg10"`. Kept short and ASCII so it fits the
+/// fixed-size header below without heap allocation.
+pub type CurveId = &'static str;
+
+/// Fixed-size prefix written ahead of the compressed body of every
+/// artifact. `curve` is zero-padded/truncated to fit; the constructors
+/// below are the only place that needs to know that.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Header {
+    version: u16,
+    curve: [u8; 32],
+}
+
+impl Header {
+    fn for_curve(curve: CurveId) -> Self {
+        let mut bytes = [0u8; 32];
+        let name = curve.as_bytes();
+        let len = name.len().min(bytes.len());
+        bytes[..len].copy_from_slice(&name[..len]);
+        Self {
+            version: FORMAT_VERSION,
+            curve: bytes,
+        }
+    }
+
+    fn write<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        writer
+            .write_all(&MAGIC)
+            .and_then(|_| writer.write_all(&self.version.to_le_bytes()))
+            .and_then(|_| writer.write_all(&self.curve))
+            .map_err(SerializationError::IoError)
+    }
+
+    fn read<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let mut magic = [0u8; 4];
+        reader
+            .read_exact(&mut magic)
+            .map_err(SerializationError::IoError)?;
+        if magic != MAGIC {
+            return Err(SerializationError::InvalidData);
+        }
+
+        let mut version = [0u8; 2];
+        reader
+            .read_exact(&mut version)
+            .map_err(SerializationError::IoError)?;
+
+        let mut curve = [0u8; 32];
+        reader
+            .read_exact(&mut curve)
+            .map_err(SerializationError::IoError)?;
+
+        Ok(Self {
+            version: u16::from_le_bytes(version),
+            curve,
+        })
+    }
+}
+
+/// Adds a versioned, header-prefixed encoding on top of an existing
+/// [`CanonicalSerialize`]/[`CanonicalDeserialize`] impl.
+///
+/// Implement this for an aggregate proving key, verifying key, or proof
+/// type and set [`Serializable::CURVE_ID`] to identify what curve and
+/// commitment scheme it was built for; [`Serializable::write_versioned`]
+/// and [`Serializable::read_versioned`] then give callers a single stable
+/// artifact format instead of each struct's `CanonicalSerialize` output
+/// being indistinguishable from any other's.
+pub trait Serializable: CanonicalSerialize + CanonicalDeserialize {
+    /// Identifies the curve and commitment scheme this type is built for,
+    /// e.g. `"bls12-381/kzg10"`.
+    const CURVE_ID: CurveId;
+
+    /// Write `self` as `MAGIC || version || curve id || compressed body`.
+    fn write_versioned<W: Write>(
+        &self,
+        mut writer: W,
+    ) -> Result<(), SerializationError> {
+        Header::for_curve(Self::CURVE_ID).write(&mut writer)?;
+        self.serialize(&mut writer)
+    }
+
+    /// Read back an artifact written by [`Serializable::write_versioned`].
+    ///
+    /// Rejects the artifact before attempting to decode its body if the
+    /// header names a different format version, or a different curve /
+    /// commitment scheme than `Self::CURVE_ID`.
+    fn read_versioned<R: Read>(
+        mut reader: R,
+    ) -> Result<Self, SerializationError> {
+        let header = Header::read(&mut reader)?;
+        let expected = Header::for_curve(Self::CURVE_ID);
+
+        if header.version != expected.version || header.curve != expected.curve
+        {
+            return Err(SerializationError::InvalidData);
+        }
+
+        Self::deserialize(&mut reader)
+    }
+}
@@ -0,0 +1,65 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) ZK-Garage. All rights reserved.
+//! A [`Transcript`] backed by a running Blake2b digest.
+
+use super::Transcript;
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+use blake2::{Blake2b, Digest};
+
+/// Byte-oriented Fiat-Shamir transcript. Every `append_*`/`challenge_scalar`
+/// call first absorbs the call's label so that two proofs which append the
+/// same values in a different order, or under different names, never
+/// collide on a challenge.
+#[derive(Clone)]
+pub struct Blake2bTranscript {
+    state: Blake2b,
+}
+
+impl Blake2bTranscript {
+    /// Start a new transcript, domain-separated by `label` (typically the
+    /// name of the protocol, e.g. `b"plonk-proof"`).
+    pub fn new(label: &'static [u8]) -> Self {
+        let mut state = Blake2b::new();
+        state.update(label);
+        Self { state }
+    }
+}
+
+impl<F> Transcript<F> for Blake2bTranscript
+where
+    F: PrimeField,
+{
+    fn append_commitment<C>(&mut self, label: &'static [u8], commitment: &C)
+    where
+        C: CanonicalSerialize,
+    {
+        self.state.update(label);
+        let mut bytes = Vec::new();
+        commitment
+            .serialize(&mut bytes)
+            .expect("commitment serialisation into a Vec cannot fail");
+        self.state.update(&bytes);
+    }
+
+    fn append_scalar(&mut self, label: &'static [u8], scalar: &F) {
+        self.state.update(label);
+        let mut bytes = Vec::new();
+        scalar
+            .serialize(&mut bytes)
+            .expect("scalar serialisation into a Vec cannot fail");
+        self.state.update(&bytes);
+    }
+
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> F {
+        self.state.update(label);
+        let digest = self.state.clone().finalize();
+        // Mix the challenge back into the running state so replaying a
+        // prefix of the transcript can never reproduce it.
+        self.state.update(&digest);
+        F::from_le_bytes_mod_order(&digest)
+    }
+}
@@ -0,0 +1,299 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) ZK-Garage. All rights reserved.
+//! Fiat-Shamir transcripts.
+//!
+//! A [`Transcript`] is the thing the prover and verifier both feed the
+//! public data of a proof into (commitments, scalars) and both squeeze
+//! round challenges out of, so that neither side has to agree on a
+//! challenge out of band. Which hash underlies that sponge is otherwise
+//! an implementation detail, so it is exposed as a trait rather than
+//! hard-coded: [`Blake2bTranscript`] is the classic byte-oriented choice,
+//! while [`PoseidonTranscript`] squeezes natively over `F`, which is what
+//! a circuit that verifies this proof recursively wants.
+//!
+//! [`Transcript::append_commitment`] is generic over the commitment type
+//! rather than tied to a `PolynomialCommitment::Commitment` associated
+//! type, so the same transcript backend can absorb both the
+//! `PC`-generic commitments used in [`crate::proof_system::widget`] and
+//! the concrete `kzg10::Commitment<E>` used by widgets that predate the
+//! generic commitment-scheme abstraction.
+
+mod blake2b;
+mod poseidon;
+
+pub use blake2b::Blake2bTranscript;
+pub use poseidon::{PoseidonConfig, PoseidonTranscript};
+
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+
+/// A Fiat-Shamir transcript over a field `F`.
+///
+/// Implementors behave like a domain-separated sponge: everything fed in
+/// through [`Transcript::append_commitment`] / [`Transcript::append_scalar`]
+/// changes every challenge squeezed afterwards through
+/// [`Transcript::challenge_scalar`]. `label` is a static domain separator
+/// (e.g. `b"alpha"`) rather than user data, so the same sequence of calls
+/// always derives the same challenges for the same proof.
+pub trait Transcript<F>
+where
+    F: PrimeField,
+{
+    /// Absorb a labelled commitment. Generic over the commitment type so
+    /// one transcript backend serves every commitment scheme in the
+    /// crate, old and new.
+    fn append_commitment<C>(&mut self, label: &'static [u8], commitment: &C)
+    where
+        C: CanonicalSerialize;
+
+    /// Absorb a labelled scalar.
+    fn append_scalar(&mut self, label: &'static [u8], scalar: &F);
+
+    /// Squeeze a labelled challenge scalar out of everything absorbed so
+    /// far, and mix the challenge itself back in so it can never be
+    /// reproduced by replaying a prefix of the transcript.
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> F;
+}
+
+/// Serialises a proof and absorbs every value into a [`Transcript`] in the
+/// same pass, so the prover never has to walk the proof a second time just
+/// to re-derive the challenges it already knows.
+pub struct TranscriptWrite<'a, T, W> {
+    transcript: &'a mut T,
+    writer: W,
+}
+
+impl<'a, T, W> TranscriptWrite<'a, T, W>
+where
+    W: Write,
+{
+    /// Wrap a transcript and the writer a proof is being serialised into.
+    pub fn new(transcript: &'a mut T, writer: W) -> Self {
+        Self { transcript, writer }
+    }
+
+    /// Serialise `commitment`, absorbing it under `label` as it is written.
+    pub fn append_commitment<F, C>(
+        &mut self,
+        label: &'static [u8],
+        commitment: &C,
+    ) -> Result<(), SerializationError>
+    where
+        F: PrimeField,
+        C: CanonicalSerialize,
+        T: Transcript<F>,
+    {
+        self.transcript.append_commitment(label, commitment);
+        commitment.serialize(&mut self.writer)
+    }
+
+    /// Serialise `scalar`, absorbing it under `label` as it is written.
+    pub fn append_scalar<F>(
+        &mut self,
+        label: &'static [u8],
+        scalar: &F,
+    ) -> Result<(), SerializationError>
+    where
+        F: PrimeField,
+        T: Transcript<F>,
+    {
+        self.transcript.append_scalar(label, scalar);
+        scalar.serialize(&mut self.writer)
+    }
+
+    /// Derive a challenge without writing anything to the proof.
+    pub fn challenge_scalar<F>(&mut self, label: &'static [u8]) -> F
+    where
+        F: PrimeField,
+        T: Transcript<F>,
+    {
+        self.transcript.challenge_scalar(label)
+    }
+
+    /// Consume the wrapper, returning the underlying writer.
+    pub fn finish(self) -> W {
+        self.writer
+    }
+}
+
+/// The dual of [`TranscriptWrite`]: deserialises a proof and absorbs every
+/// value into a [`Transcript`] as it is read, so the verifier derives the
+/// same challenges the prover did without a second traversal.
+pub struct TranscriptRead<'a, T, R> {
+    transcript: &'a mut T,
+    reader: R,
+}
+
+impl<'a, T, R> TranscriptRead<'a, T, R>
+where
+    R: Read,
+{
+    /// Wrap a transcript and the reader a proof is being deserialised from.
+    pub fn new(transcript: &'a mut T, reader: R) -> Self {
+        Self { transcript, reader }
+    }
+
+    /// Deserialise a commitment, absorbing it under `label` as it is read.
+    pub fn append_commitment<F, C>(
+        &mut self,
+        label: &'static [u8],
+    ) -> Result<C, SerializationError>
+    where
+        F: PrimeField,
+        C: CanonicalSerialize + CanonicalDeserialize,
+        T: Transcript<F>,
+    {
+        let commitment = C::deserialize(&mut self.reader)?;
+        self.transcript.append_commitment(label, &commitment);
+        Ok(commitment)
+    }
+
+    /// Deserialise a scalar, absorbing it under `label` as it is read.
+    pub fn append_scalar<F>(
+        &mut self,
+        label: &'static [u8],
+    ) -> Result<F, SerializationError>
+    where
+        F: PrimeField + CanonicalDeserialize,
+        T: Transcript<F>,
+    {
+        let scalar = F::deserialize(&mut self.reader)?;
+        self.transcript.append_scalar(label, &scalar);
+        Ok(scalar)
+    }
+
+    /// Derive a challenge without reading anything from the proof.
+    pub fn challenge_scalar<F>(&mut self, label: &'static [u8]) -> F
+    where
+        F: PrimeField,
+        T: Transcript<F>,
+    {
+        self.transcript.challenge_scalar(label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr;
+
+    fn tiny_poseidon_config() -> PoseidonConfig<Fr> {
+        // Not a secure parameter set - just enough rounds/width to
+        // exercise the sponge mechanics in a test.
+        let width = 2;
+        let total_rounds = 4;
+        PoseidonConfig {
+            full_rounds: 4,
+            partial_rounds: 0,
+            alpha: 5,
+            ark: vec![vec![Fr::from(7u64); width]; total_rounds],
+            mds: vec![vec![Fr::from(2u64), Fr::from(1u64)], vec![
+                Fr::from(1u64),
+                Fr::from(3u64),
+            ]],
+            rate: 1,
+            capacity: 1,
+        }
+    }
+
+    #[test]
+    fn blake2b_transcript_is_deterministic() {
+        let mut t1 = Blake2bTranscript::new(b"test");
+        let mut t2 = Blake2bTranscript::new(b"test");
+
+        t1.append_scalar(b"a", &Fr::from(1u64));
+        t2.append_scalar(b"a", &Fr::from(1u64));
+
+        assert_eq!(
+            Transcript::<Fr>::challenge_scalar(&mut t1, b"alpha"),
+            Transcript::<Fr>::challenge_scalar(&mut t2, b"alpha"),
+        );
+    }
+
+    #[test]
+    fn blake2b_transcript_is_domain_separated() {
+        let mut same_value_different_label = Blake2bTranscript::new(b"test");
+        same_value_different_label.append_scalar(b"b", &Fr::from(1u64));
+
+        let mut same_label_different_value = Blake2bTranscript::new(b"test");
+        same_label_different_value.append_scalar(b"a", &Fr::from(2u64));
+
+        assert_ne!(
+            Transcript::<Fr>::challenge_scalar(
+                &mut same_value_different_label,
+                b"alpha"
+            ),
+            Transcript::<Fr>::challenge_scalar(
+                &mut same_label_different_value,
+                b"alpha"
+            ),
+        );
+    }
+
+    #[test]
+    fn blake2b_challenge_cannot_be_replayed_from_a_prefix() {
+        let mut transcript = Blake2bTranscript::new(b"test");
+        transcript.append_scalar(b"a", &Fr::from(1u64));
+
+        let first: Fr = Transcript::<Fr>::challenge_scalar(&mut transcript, b"alpha");
+        let second: Fr = Transcript::<Fr>::challenge_scalar(&mut transcript, b"alpha");
+
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn poseidon_transcript_is_deterministic() {
+        let mut t1 = PoseidonTranscript::new(tiny_poseidon_config());
+        let mut t2 = PoseidonTranscript::new(tiny_poseidon_config());
+
+        t1.append_scalar(b"a", &Fr::from(1u64));
+        t2.append_scalar(b"a", &Fr::from(1u64));
+
+        assert_eq!(
+            Transcript::<Fr>::challenge_scalar(&mut t1, b"alpha"),
+            Transcript::<Fr>::challenge_scalar(&mut t2, b"alpha"),
+        );
+    }
+
+    #[test]
+    fn poseidon_transcript_is_domain_separated() {
+        let mut a = PoseidonTranscript::new(tiny_poseidon_config());
+        a.append_scalar(b"a", &Fr::from(1u64));
+
+        let mut b = PoseidonTranscript::new(tiny_poseidon_config());
+        b.append_scalar(b"a", &Fr::from(2u64));
+
+        assert_ne!(
+            Transcript::<Fr>::challenge_scalar(&mut a, b"alpha"),
+            Transcript::<Fr>::challenge_scalar(&mut b, b"alpha"),
+        );
+    }
+
+    #[test]
+    fn transcript_write_then_read_roundtrips_and_agrees_on_challenges() {
+        let mut write_transcript = Blake2bTranscript::new(b"test");
+        let mut bytes = Vec::new();
+        {
+            let mut writer =
+                TranscriptWrite::new(&mut write_transcript, &mut bytes);
+            writer
+                .append_scalar::<Fr>(b"a", &Fr::from(42u64))
+                .unwrap();
+        }
+        let expected: Fr =
+            Transcript::<Fr>::challenge_scalar(&mut write_transcript, b"alpha");
+
+        let mut read_transcript = Blake2bTranscript::new(b"test");
+        let mut reader = &bytes[..];
+        let mut read = TranscriptRead::new(&mut read_transcript, &mut reader);
+        let scalar: Fr = read.append_scalar(b"a").unwrap();
+        assert_eq!(scalar, Fr::from(42u64));
+
+        let actual: Fr =
+            Transcript::<Fr>::challenge_scalar(&mut read_transcript, b"alpha");
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,149 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) ZK-Garage. All rights reserved.
+//! A [`Transcript`] backed by a native Poseidon sponge over `F`, so that a
+//! circuit verifying this proof recursively can re-derive the same
+//! challenges without ever leaving its native field.
+
+use super::Transcript;
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+
+/// The round constants and MDS matrix a [`PoseidonTranscript`] permutes
+/// with. These are curve-specific and are expected to be generated offline
+/// (e.g. via the standard Poseidon parameter generation script) and
+/// supplied by the caller, the same way other curve-dependent constants
+/// are threaded through this crate.
+#[derive(Clone, Debug)]
+pub struct PoseidonConfig<F: PrimeField> {
+    /// Number of full S-box rounds, split evenly before and after the
+    /// partial rounds.
+    pub full_rounds: usize,
+    /// Number of partial (single S-box) rounds.
+    pub partial_rounds: usize,
+    /// S-box exponent.
+    pub alpha: u64,
+    /// Round constants, one row of `width` elements per round.
+    pub ark: Vec<Vec<F>>,
+    /// The `width x width` MDS matrix.
+    pub mds: Vec<Vec<F>>,
+    /// Number of elements absorbed/squeezed per permutation (rate).
+    pub rate: usize,
+    /// Number of elements reserved for capacity (never output directly).
+    pub capacity: usize,
+}
+
+impl<F: PrimeField> PoseidonConfig<F> {
+    /// Sponge width, i.e. `rate + capacity`.
+    fn width(&self) -> usize {
+        self.rate + self.capacity
+    }
+}
+
+/// Native Poseidon sponge transcript. Labels are folded into the absorbed
+/// field elements (rather than hashed as bytes) so the whole transcript
+/// stays inside `F`, which is what makes this backend cheap to verify
+/// inside a circuit.
+pub struct PoseidonTranscript<F: PrimeField> {
+    config: PoseidonConfig<F>,
+    state: Vec<F>,
+    /// Elements absorbed since the last permutation, not yet mixed in.
+    buffer: Vec<F>,
+}
+
+impl<F: PrimeField> PoseidonTranscript<F> {
+    /// Start a new sponge in the zero state.
+    pub fn new(config: PoseidonConfig<F>) -> Self {
+        let width = config.width();
+        Self {
+            config,
+            state: vec![F::zero(); width],
+            buffer: Vec::new(),
+        }
+    }
+
+    fn label_to_field(label: &'static [u8]) -> F {
+        F::from_le_bytes_mod_order(label)
+    }
+
+    fn absorb_field(&mut self, value: F) {
+        self.buffer.push(value);
+        if self.buffer.len() == self.config.rate {
+            self.permute();
+        }
+    }
+
+    /// Mix any buffered input into the state and run the permutation.
+    fn permute(&mut self) {
+        for (i, value) in self.buffer.drain(..).enumerate() {
+            self.state[i] += value;
+        }
+
+        let half_full = self.config.full_rounds / 2;
+        for round in 0..(self.config.full_rounds + self.config.partial_rounds) {
+            for (i, s) in self.state.iter_mut().enumerate() {
+                *s += self.config.ark[round][i];
+            }
+
+            if round < half_full || round >= half_full + self.config.partial_rounds {
+                for s in self.state.iter_mut() {
+                    *s = s.pow([self.config.alpha]);
+                }
+            } else {
+                self.state[0] = self.state[0].pow([self.config.alpha]);
+            }
+
+            let width = self.config.width();
+            let mut next = vec![F::zero(); width];
+            for (i, row) in self.config.mds.iter().enumerate() {
+                for (j, mds_ij) in row.iter().enumerate() {
+                    next[i] += *mds_ij * self.state[j];
+                }
+            }
+            self.state = next;
+        }
+    }
+
+    fn squeeze_field(&mut self) -> F {
+        if !self.buffer.is_empty() {
+            self.permute();
+        }
+        let out = self.state[0];
+        // A challenge must not be reusable as an absorbed value: running
+        // the permutation again before the next squeeze/absorb keeps the
+        // visible output a one-way function of everything absorbed so far.
+        self.permute();
+        out
+    }
+}
+
+impl<F> Transcript<F> for PoseidonTranscript<F>
+where
+    F: PrimeField,
+{
+    fn append_commitment<C>(&mut self, label: &'static [u8], commitment: &C)
+    where
+        C: CanonicalSerialize,
+    {
+        self.absorb_field(Self::label_to_field(label));
+        let mut bytes = Vec::new();
+        commitment
+            .serialize(&mut bytes)
+            .expect("commitment serialisation into a Vec cannot fail");
+        for chunk in bytes.chunks(F::size_in_bits() / 8) {
+            self.absorb_field(F::from_le_bytes_mod_order(chunk));
+        }
+    }
+
+    fn append_scalar(&mut self, label: &'static [u8], scalar: &F) {
+        self.absorb_field(Self::label_to_field(label));
+        self.absorb_field(*scalar);
+    }
+
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> F {
+        self.absorb_field(Self::label_to_field(label));
+        self.squeeze_field()
+    }
+}
@@ -7,20 +7,45 @@
 use crate::proof_system::linearisation_poly::ProofEvaluations;
 use ark_ec::{PairingEngine, TEModelParameters};
 use ark_poly_commit::kzg10::Commitment;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use plonk_core::serialisation::Serializable;
+use plonk_core::transcript::Transcript;
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(
+    CanonicalDeserialize,
+    CanonicalSerialize,
+    Debug,
+    PartialEq,
+    Eq,
+    Copy,
+    Clone,
+)]
 pub(crate) struct VerifierKey<E: PairingEngine, P: TEModelParameters> {
     pub(crate) q_variable_group_add: Commitment<E>,
 }
 
 impl<E: PairingEngine, P: TEModelParameters> VerifierKey<E, P> {
-    pub(crate) fn compute_linearisation_commitment(
+    /// Computes the linearisation commitment for the curve-addition gate.
+    ///
+    /// Absorbs `q_variable_group_add` - the only commitment this gate
+    /// owns - and squeezes `curve_add_separation_challenge` from
+    /// `transcript` rather than taking it as an argument, tying the
+    /// challenge to this key's own selector commitment.
+    pub(crate) fn compute_linearisation_commitment<T>(
         &self,
-        curve_add_separation_challenge: &E::Fr,
+        transcript: &mut T,
         scalars: &mut Vec<E::Fr>,
         points: &mut Vec<E::G1Affine>,
         evaluations: &ProofEvaluations<E::Fr>,
-    ) {
+    ) where
+        T: Transcript<E::Fr>,
+    {
+        transcript.append_commitment(
+            b"q_variable_group_add",
+            &self.q_variable_group_add,
+        );
+        let curve_add_separation_challenge =
+            transcript.challenge_scalar(b"curve_add_separation_challenge");
         let kappa = curve_add_separation_challenge.square();
 
         let x_1 = evaluations.a_eval;
@@ -56,3 +81,70 @@ impl<E: PairingEngine, P: TEModelParameters> VerifierKey<E, P> {
         points.push(self.q_variable_group_add.0);
     }
 }
+
+// `VerifierKey` is generic over the pairing engine and Edwards curve
+// parameters, but `Serializable::CURVE_ID` needs a fixed string per
+// on-disk format, so it's implemented here for the one instantiation
+// this repo actually produces keys for: JubJub over BLS12-381.
+impl Serializable
+    for VerifierKey<
+        ark_bls12_381::Bls12_381,
+        ark_ed_on_bls12_381::EdwardsParameters,
+    >
+{
+    const CURVE_ID: plonk_core::serialisation::CurveId = "bls12-381/jubjub";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::{Bls12_381, Fr, G1Affine};
+    use ark_ec::AffineCurve;
+    use ark_ed_on_bls12_381::EdwardsParameters;
+    use ark_ff::UniformRand;
+    use ark_std::test_rng;
+
+    type TestVerifierKey = VerifierKey<Bls12_381, EdwardsParameters>;
+
+    fn sample_key() -> TestVerifierKey {
+        let rng = &mut test_rng();
+        VerifierKey {
+            q_variable_group_add: Commitment(
+                (G1Affine::prime_subgroup_generator() * Fr::rand(rng))
+                    .into(),
+            ),
+        }
+    }
+
+    #[test]
+    fn write_then_read_versioned_roundtrips() {
+        let key = sample_key();
+
+        let mut bytes = Vec::new();
+        key.write_versioned(&mut bytes).unwrap();
+
+        let read_back = TestVerifierKey::read_versioned(&bytes[..]).unwrap();
+        assert_eq!(key, read_back);
+    }
+
+    #[test]
+    fn read_versioned_rejects_wrong_magic() {
+        let key = sample_key();
+        let mut bytes = Vec::new();
+        key.write_versioned(&mut bytes).unwrap();
+        bytes[0] = bytes[0].wrapping_add(1);
+
+        assert!(TestVerifierKey::read_versioned(&bytes[..]).is_err());
+    }
+
+    #[test]
+    fn read_versioned_rejects_mismatched_curve() {
+        let key = sample_key();
+        let mut bytes = Vec::new();
+        key.write_versioned(&mut bytes).unwrap();
+        // Curve id starts right after the 4-byte magic and 2-byte version.
+        bytes[6] = b'X';
+
+        assert!(TestVerifierKey::read_versioned(&bytes[..]).is_err());
+    }
+}